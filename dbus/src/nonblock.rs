@@ -4,26 +4,337 @@
 //! When async/await is stable, expect more here.
 
 use crate::{Error, Message};
-use crate::channel::{MatchingReceiver, Channel, Sender};
+use crate::channel::{MatchingReceiver, Channel, Sender, BusType};
 use crate::strings::{BusName, Path, Interface, Member};
-use crate::arg::{AppendAll, ReadAll, IterAppend};
+use crate::arg::{AppendAll, ReadAll, IterAppend, MessageItem};
 use crate::message::MatchRule;
 
 use std::sync::{Arc, Mutex};
-use std::{future, task, pin, mem};
-use std::collections::{HashMap, BTreeMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{future, task, pin, mem, ops};
+use std::collections::{HashMap, BTreeMap, VecDeque};
 use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 pub mod stdintf;
 
-/// Thread local + async Connection 
+/// Default timeout for a method call reply, matching the one libdbus itself uses.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// Default capacity of the bounded channel backing a [`MessageStream`]; once full,
+/// further matching messages are dropped rather than blocking the reactor.
+const DEFAULT_STREAM_CAPACITY: usize = 64;
+
+/// What a registered filter/method-handler callback wants to happen next, returned from
+/// `call_filter` instead of a plain `bool` so a handler for an incoming method call can
+/// answer asynchronously.
+pub enum FilterResult<Fut> {
+    /// The message was handled synchronously; keep the filter registered.
+    Keep,
+    /// Deregister the filter (the old "return false" case).
+    Done,
+    /// This is an async method handler: `fut` resolves to the method's return arguments
+    /// (or an error), which the connection sends back once ready. The filter stays
+    /// registered so it can keep handling later calls.
+    ///
+    /// **Important:** `fut` is only ever polled from `process_all`, and only once per
+    /// call (see `poll_pending_generic`). There is no executor here to wake it up in
+    /// between - `process_all` itself only runs when the reactor driving this connection
+    /// sees socket activity. A `fut` that becomes ready because of something *other* than
+    /// incoming D-Bus traffic (a timer, a channel, another thread) will sit un-polled,
+    /// and its caller will hang, until the next unrelated message arrives on this
+    /// connection. Don't rely on external wakeups for these futures unless something else
+    /// also prods `process_all`.
+    Pending(Fut),
+}
+
+/// Key for the `pending` map of in-flight async method-handler futures: the call's
+/// sender plus its serial. D-Bus only guarantees serials are unique per sender, so the
+/// serial alone can't tell two different clients' in-flight calls apart.
+type PendingKey = (Option<String>, u32);
+
+fn pending_key(msg: &Message) -> PendingKey {
+    (msg.sender().map(|s| s.to_string()), msg.get_serial())
+}
+
+/// Boxed future behind a pending async method-call reply, thread-local flavor.
+type LocalPendingFut = pin::Pin<Box<dyn future::Future<Output = Result<Vec<MessageItem>, Error>>>>;
+/// Boxed future behind a pending async method-call reply, `Send` flavor (for [`SyncConnection`]).
+type SyncPendingFut = pin::Pin<Box<dyn future::Future<Output = Result<Vec<MessageItem>, Error>> + Send>>;
+
+/// A no-op `Waker`, used when polling in-flight method-handler futures from `process_all`.
+///
+/// This module doesn't have a real executor to park on: `process_all` just polls every
+/// pending future once per reactor tick regardless of whether it was "woken", so the
+/// waker itself doesn't need to do anything.
+fn noop_waker() -> task::Waker {
+    fn no_op(_: *const ()) {}
+    fn clone_raw(_: *const ()) -> task::RawWaker { RAW }
+    static VTABLE: task::RawWakerVTable = task::RawWakerVTable::new(clone_raw, no_op, no_op, no_op);
+    const RAW: task::RawWaker = task::RawWaker::new(std::ptr::null(), &VTABLE);
+    unsafe { task::Waker::from_raw(RAW) }
+}
+
+/// Internal helper trait that abstracts over the interior-mutability strategy used to
+/// store replies, filters and pending timeouts, so the reactor logic below can be
+/// shared between [`LocalConnection`] (thread-local, `RefCell`/`Cell`) and
+/// [`SyncConnection`] (`Send + Sync`, `Mutex`/`AtomicU32`).
+pub(crate) trait Dispatch: Sized {
+    type ReplyCb;
+    type FilterCb;
+    type PendingFut;
+
+    fn channel(&self) -> &Channel;
+    fn take_reply(&self, serial: u32) -> Option<Self::ReplyCb>;
+    fn take_filter_for(&self, msg: &Message) -> Option<(u32, MatchRule<'static>, Self::FilterCb)>;
+    fn reinsert_filter(&self, id: u32, m: MatchRule<'static>, f: Self::FilterCb);
+    fn call_reply(f: Self::ReplyCb, msg: Result<Message, Error>, conn: &Self);
+    fn call_filter(f: &mut Self::FilterCb, msg: Message, conn: &Self) -> FilterResult<Self::PendingFut>;
+
+    fn default_timeout(&self) -> Duration;
+    fn set_default_timeout(&self, timeout: Duration);
+    fn insert_timeout(&self, serial: u32, deadline: Instant);
+    fn remove_timeout(&self, serial: u32);
+    fn next_timeout(&self) -> Option<Instant>;
+    fn take_expired_timeouts(&self, now: Instant) -> Vec<u32>;
+
+    /// Sends `msg` and records both `f` and `deadline` for the resulting serial in one
+    /// critical section, so every serial tracked for a reply has exactly one timeout
+    /// deadline, and vice versa, with no window where a concurrent `take_reply` (e.g. from
+    /// another thread's `process_all` on a `SyncConnection`) can observe one without the
+    /// other.
+    fn send_with_reply_and_timeout(&self, msg: Message, f: Self::ReplyCb, deadline: Instant) -> Result<u32, ()>;
+
+    /// Stores an in-flight async method-handler future, keyed by the incoming call's
+    /// (sender, serial) pair. D-Bus serials are only unique per sender - two different
+    /// clients routinely send calls with the same serial - so the serial alone isn't a
+    /// safe key for a server handling several clients at once.
+    fn insert_pending(&self, orig: Message, fut: Self::PendingFut);
+    /// Drains all in-flight async method-handler futures, so they can be polled outside
+    /// of any lock/borrow on the connection's internal state.
+    fn take_pending(&self) -> Vec<(Message, Self::PendingFut)>;
+    /// Puts a still-pending future back after a poll that returned `Poll::Pending`.
+    fn reinsert_pending(&self, orig: Message, fut: Self::PendingFut);
+}
+
+fn read_write_generic<C: Dispatch>(conn: &C) -> Result<(), Error> {
+    conn.channel().read_write(Some(Default::default())).map_err(|_| Error::new_custom("org.freedesktop.DBus.Error.Failed", "Read/write failed"))
+}
+
+fn process_all_generic<C>(conn: &C)
+where
+    C: Dispatch,
+    C::PendingFut: future::Future<Output = Result<Vec<MessageItem>, Error>> + Unpin,
+{
+    process_timeouts_generic(conn);
+    poll_pending_generic(conn);
+    while let Some(msg) = conn.channel().pop_message() {
+        if let Some(serial) = msg.get_reply_serial() {
+            if let Some(f) = conn.take_reply(serial) {
+                conn.remove_timeout(serial);
+                C::call_reply(f, Ok(msg), conn);
+                continue;
+            }
+        }
+        if let Some((id, m, mut f)) = conn.take_filter_for(&msg) {
+            let orig = msg.clone();
+            match C::call_filter(&mut f, msg, conn) {
+                FilterResult::Keep => conn.reinsert_filter(id, m, f),
+                FilterResult::Done => {}
+                FilterResult::Pending(fut) => {
+                    conn.reinsert_filter(id, m, f);
+                    conn.insert_pending(orig, fut);
+                }
+            }
+            continue;
+        }
+        if let Some(reply) = crate::channel::default_reply(&msg) {
+            let _ = conn.channel().send(reply);
+        }
+    }
+}
+
+/// Polls every in-flight async method-handler future once. Anything that resolves has
+/// its method return (or error) built from the stored original call and sent back,
+/// addressed via that call's `reply_serial`; anything still pending is kept for the next
+/// tick. Pending futures are keyed by (sender, serial), so entries are grouped by sender
+/// and polled in ascending serial order within each sender - the only order that's
+/// actually meaningful, since serials are only assigned in increasing order per sender.
+///
+/// Each future is polled with a no-op waker (see `noop_waker`) and only from here, i.e.
+/// only when `process_all` runs. There's no executor to act on a real wakeup in between
+/// calls, so a handler future that only becomes ready due to something outside of D-Bus
+/// traffic won't be polled again until the next `process_all`; see the warning on
+/// `FilterResult::Pending`.
+fn poll_pending_generic<C>(conn: &C)
+where
+    C: Dispatch,
+    C::PendingFut: future::Future<Output = Result<Vec<MessageItem>, Error>> + Unpin,
+{
+    let waker = noop_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+    for (orig, mut fut) in conn.take_pending() {
+        match future::Future::poll(pin::Pin::new(&mut fut), &mut ctx) {
+            task::Poll::Pending => conn.reinsert_pending(orig, fut),
+            task::Poll::Ready(Ok(items)) => {
+                let mut reply = orig.method_return();
+                {
+                    let mut iter = IterAppend::new(&mut reply);
+                    for item in items { item.append(&mut iter); }
+                }
+                let _ = conn.channel().send(reply);
+            }
+            task::Poll::Ready(Err(err)) => {
+                let reply = Message::new_error(&orig, err.name(), err.message().unwrap_or(""));
+                let _ = conn.channel().send(reply);
+            }
+        }
+    }
+}
+
+/// Walks expired timeout deadlines and wakes their `MethodReply` with a timeout error.
+fn process_timeouts_generic<C: Dispatch>(conn: &C) {
+    for serial in conn.take_expired_timeouts(Instant::now()) {
+        if let Some(f) = conn.take_reply(serial) {
+            let err = Error::new_custom("org.freedesktop.DBus.Error.Timeout", "Message reply timed out");
+            C::call_reply(f, Err(err), conn);
+        }
+    }
+}
+
+fn send_with_reply_and_timeout_generic<C>(conn: &C, msg: Message, timeout: Duration, f: C::F) -> Result<u32, ()>
+where C: NonblockReply + Dispatch<ReplyCb = <C as NonblockReply>::F> {
+    conn.send_with_reply_and_timeout(msg, f, Instant::now() + timeout)
+}
+
+/// Builds the `NonblockReply::F` callback that resolves a `MethodReply`.
+///
+/// This only exists so `Proxy::method_call` can be written once, generically over the
+/// connection type `T`, instead of once per connection type: the callback's signature
+/// (in particular, whether it needs to be `Send`) differs between [`Connection`] and
+/// [`SyncConnection`], so each connection type builds its own.
+pub(crate) trait MakeReplyCb: NonblockReply + Sized {
+    /// The trait object type behind a `MethodReply`'s cancellation token for this
+    /// connection type: a plain `dyn FnOnce()` for `Connection`, but `dyn FnOnce() + Send
+    /// + Sync` for `SyncConnection`, so a `MethodReply` built over a `Send` connection is
+    /// itself `Send` (see [`MakeCancel`]).
+    type Cancel: FnOnce() + ?Sized + 'static;
+    fn make_reply_cb(mr: Arc<Mutex<MRInner>>) -> Self::F;
+}
+
+/// Builds the boxed cancellation callback stored in a `MethodReply`, generic over the
+/// `Proxy`'s connection handle `C` (e.g. `Rc<Connection>` or `Arc<SyncConnection>`) so it
+/// can require `C: Send + Sync` only where that's actually available.
+pub(crate) trait MakeCancel<C>: MakeReplyCb {
+    fn make_cancel(conn: C, serial: u32) -> Box<Self::Cancel>;
+}
+
+/// A minimal stand-in for `futures::Stream`, so this module doesn't have to pull in the
+/// `futures` crate for just this one trait. A real async runtime integration (e.g.
+/// dbus-tokio) can bridge a `MessageStream` into its own `Stream` type trivially, since
+/// the `poll_next` signature matches.
+pub trait Stream {
+    /// The type of item yielded by the stream; always `Message` for this module's streams.
+    type Item;
+    /// Attempts to pull the next message out of the stream, registering the waker for later
+    /// wakeups if none is available yet.
+    fn poll_next(self: pin::Pin<&mut Self>, ctx: &mut task::Context) -> task::Poll<Option<Self::Item>>;
+}
+
+/// The bounded queue shared between a registered filter callback and the [`MessageStream`]
+/// it feeds; lives behind an `Arc` so both sides can outlive the other.
+/// The queue and its waker live behind one mutex: `push` and `poll` both need to look at
+/// the queue and the waker together, and splitting them into two locks opens a
+/// lost-wakeup race (a `push` between `poll`'s queue-check and its waker-store would
+/// enqueue the message but find no waker to call).
+struct StreamQueueInner {
+    messages: VecDeque<Message>,
+    waker: Option<task::Waker>,
+}
+
+pub(crate) struct StreamQueue {
+    inner: Mutex<StreamQueueInner>,
+    capacity: usize,
+}
+
+impl StreamQueue {
+    fn new(capacity: usize) -> Self {
+        StreamQueue { inner: Mutex::new(StreamQueueInner { messages: VecDeque::new(), waker: None }), capacity }
+    }
+
+    /// Called from the (possibly `Send`) filter callback; drops the message if the
+    /// stream's consumer isn't keeping up rather than blocking the reactor. `capacity`
+    /// is set when the stream is created - see `add_match_stream_with_capacity` and
+    /// `Proxy::match_signal_stream_with_capacity` - so a consumer that needs more
+    /// headroom than `DEFAULT_STREAM_CAPACITY` can ask for it up front.
+    fn push(&self, msg: Message) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.messages.len() < self.capacity {
+            inner.messages.push_back(msg);
+        }
+        if let Some(waker) = inner.waker.take() { waker.wake() }
+    }
+
+    fn poll(&self, ctx: &mut task::Context) -> task::Poll<Option<Message>> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(msg) = inner.messages.pop_front() {
+            return task::Poll::Ready(Some(msg));
+        }
+        inner.waker = Some(ctx.waker().clone());
+        task::Poll::Pending
+    }
+}
+
+/// Builds the `MatchingReceiver::F` callback that feeds a [`MessageStream`]'s queue.
+///
+/// Exists for the same reason as [`MakeReplyCb`]: the callback's `Send`-ness differs
+/// between [`Connection`] and [`SyncConnection`], so each connection type builds its own.
+pub(crate) trait MakeFilterCb: MatchingReceiver + Sized {
+    fn make_filter_cb(queue: Arc<StreamQueue>) -> Self::F;
+}
+
+/// A stream of messages matching a [`MatchRule`], created via `add_match_stream` on a
+/// connection, or `Proxy::match_signal_stream`.
+///
+/// The underlying filter is deregistered via `stop_receive` when the stream is dropped.
+pub struct MessageStream<C> where C: ops::Deref, C::Target: MatchingReceiver {
+    queue: Arc<StreamQueue>,
+    id: u32,
+    connection: C,
+}
+
+impl<C> Stream for MessageStream<C> where C: ops::Deref, C::Target: MatchingReceiver {
+    type Item = Message;
+    fn poll_next(self: pin::Pin<&mut Self>, ctx: &mut task::Context) -> task::Poll<Option<Message>> {
+        self.queue.poll(ctx)
+    }
+}
+
+impl<C> Drop for MessageStream<C> where C: ops::Deref, C::Target: MatchingReceiver {
+    fn drop(&mut self) {
+        self.connection.stop_receive(self.id);
+    }
+}
+
+/// Thread local + async Connection
 pub struct Connection {
     channel: Channel,
-    replies: RefCell<HashMap<u32, Box<dyn FnOnce(Message, &Connection)>>>,
-    filters: RefCell<BTreeMap<u32, (MatchRule<'static>, Box<dyn FnMut(Message, &Connection) -> bool>)>>,
+    replies: RefCell<HashMap<u32, Box<dyn FnOnce(Result<Message, Error>, &Connection)>>>,
+    filters: RefCell<BTreeMap<u32, (MatchRule<'static>, Box<dyn FnMut(Message, &Connection) -> FilterResult<LocalPendingFut>>)>>,
     filter_nextid: Cell<u32>,
+    timeouts: RefCell<BTreeMap<Instant, Vec<u32>>>,
+    timeout_deadlines: RefCell<HashMap<u32, Instant>>,
+    default_timeout: Cell<Duration>,
+    unique_name: RefCell<Option<BusName<'static>>>,
+    pending: RefCell<BTreeMap<PendingKey, (Message, LocalPendingFut)>>,
 }
 
+/// Alias for [`Connection`], for symmetry with [`SyncConnection`].
+///
+/// This is the connection to use when you don't need to share it between threads.
+pub type LocalConnection = Connection;
+
 impl AsRef<Channel> for Connection {
     fn as_ref(&self) -> &Channel { &self.channel }
 }
@@ -35,6 +346,11 @@ impl From<Channel> for Connection {
             replies: Default::default(),
             filters: Default::default(),
             filter_nextid: Default::default(),
+            timeouts: Default::default(),
+            timeout_deadlines: Default::default(),
+            default_timeout: Cell::new(DEFAULT_TIMEOUT),
+            unique_name: Default::default(),
+            pending: Default::default(),
         }
     }
 }
@@ -50,18 +366,22 @@ pub trait NonblockReply {
 }
 
 impl NonblockReply for Connection {
-    type F = Box<dyn FnOnce(Message, &Connection)>;
+    type F = Box<dyn FnOnce(Result<Message, Error>, &Connection)>;
     fn send_with_reply(&self, msg: Message, f: Self::F) -> Result<u32, ()> {
         self.channel.send(msg).map(|x| {
             self.replies.borrow_mut().insert(x, f);
             x
         })
     }
-    fn cancel_reply(&self, id: u32) -> Option<Self::F> { self.replies.borrow_mut().remove(&id) }
+    fn cancel_reply(&self, id: u32) -> Option<Self::F> {
+        let f = self.replies.borrow_mut().remove(&id);
+        if f.is_some() { self.remove_timeout(id); }
+        f
+    }
 }
 
 impl MatchingReceiver for Connection {
-    type F = Box<dyn FnMut(Message, &Connection) -> bool>;
+    type F = Box<dyn FnMut(Message, &Connection) -> FilterResult<LocalPendingFut>>;
     fn start_receive(&self, m: MatchRule<'static>, f: Self::F) -> u32 {
         let id = self.filter_nextid.get();
         self.filter_nextid.set(id+1);
@@ -73,49 +393,416 @@ impl MatchingReceiver for Connection {
     }
 }
 
+impl Dispatch for Connection {
+    type ReplyCb = Box<dyn FnOnce(Result<Message, Error>, &Connection)>;
+    type FilterCb = Box<dyn FnMut(Message, &Connection) -> FilterResult<LocalPendingFut>>;
+    type PendingFut = LocalPendingFut;
+
+    fn channel(&self) -> &Channel { &self.channel }
+    fn take_reply(&self, serial: u32) -> Option<Self::ReplyCb> { self.replies.borrow_mut().remove(&serial) }
+    fn take_filter_for(&self, msg: &Message) -> Option<(u32, MatchRule<'static>, Self::FilterCb)> {
+        let mut filters = self.filters.borrow_mut();
+        let k = filters.iter_mut().find(|(_, v)| v.0.matches(msg)).map(|(k, _)| *k)?;
+        let (m, f) = filters.remove(&k).unwrap();
+        Some((k, m, f))
+    }
+    fn reinsert_filter(&self, id: u32, m: MatchRule<'static>, f: Self::FilterCb) {
+        self.filters.borrow_mut().insert(id, (m, f));
+    }
+    fn call_reply(f: Self::ReplyCb, msg: Result<Message, Error>, conn: &Self) { f(msg, conn) }
+    fn call_filter(f: &mut Self::FilterCb, msg: Message, conn: &Self) -> FilterResult<Self::PendingFut> { f(msg, conn) }
+
+    fn insert_pending(&self, orig: Message, fut: Self::PendingFut) {
+        self.pending.borrow_mut().insert(pending_key(&orig), (orig, fut));
+    }
+    fn take_pending(&self) -> Vec<(Message, Self::PendingFut)> {
+        mem::replace(&mut *self.pending.borrow_mut(), BTreeMap::new()).into_iter().map(|(_, v)| v).collect()
+    }
+    fn reinsert_pending(&self, orig: Message, fut: Self::PendingFut) {
+        self.pending.borrow_mut().insert(pending_key(&orig), (orig, fut));
+    }
+
+    fn default_timeout(&self) -> Duration { self.default_timeout.get() }
+    fn set_default_timeout(&self, timeout: Duration) { self.default_timeout.set(timeout) }
+    fn insert_timeout(&self, serial: u32, deadline: Instant) {
+        self.timeouts.borrow_mut().entry(deadline).or_insert_with(Vec::new).push(serial);
+        self.timeout_deadlines.borrow_mut().insert(serial, deadline);
+    }
+    fn remove_timeout(&self, serial: u32) {
+        if let Some(deadline) = self.timeout_deadlines.borrow_mut().remove(&serial) {
+            let mut timeouts = self.timeouts.borrow_mut();
+            if let Some(serials) = timeouts.get_mut(&deadline) {
+                serials.retain(|&s| s != serial);
+                if serials.is_empty() { timeouts.remove(&deadline); }
+            }
+        }
+    }
+    fn next_timeout(&self) -> Option<Instant> { self.timeouts.borrow().keys().next().copied() }
+    fn take_expired_timeouts(&self, now: Instant) -> Vec<u32> {
+        let mut timeouts = self.timeouts.borrow_mut();
+        let expired: Vec<Instant> = timeouts.range(..=now).map(|(k, _)| *k).collect();
+        let mut serials = Vec::new();
+        for k in expired { serials.extend(timeouts.remove(&k).unwrap_or_default()); }
+        drop(timeouts);
+        let mut deadlines = self.timeout_deadlines.borrow_mut();
+        for s in &serials { deadlines.remove(s); }
+        serials
+    }
+
+    fn send_with_reply_and_timeout(&self, msg: Message, f: Self::ReplyCb, deadline: Instant) -> Result<u32, ()> {
+        let serial = self.channel.send(msg)?;
+        let mut replies = self.replies.borrow_mut();
+        replies.insert(serial, f);
+        self.insert_timeout(serial, deadline);
+        Ok(serial)
+    }
+}
 
 impl Connection {
     /// Reads/writes data to the connection, without blocking.
     ///
     /// This is usually called from the reactor when there is input on the file descriptor.
-    pub fn read_write(&self) -> Result<(), Error> {
-        self.channel.read_write(Some(Default::default())).map_err(|_| Error::new_custom("org.freedesktop.DBus.Error.Failed", "Read/write failed"))
-    }
+    pub fn read_write(&self) -> Result<(), Error> { read_write_generic(self) }
 
     /// Dispatches all pending messages, without blocking.
     ///
+    /// This also processes expired method call timeouts; see `process_timeouts`.
     /// This is usually called from the reactor only, after read_write.
-    pub fn process_all(&self) {
-        while let Some(msg) = self.channel.pop_message() {
-            if let Some(serial) = msg.get_reply_serial() {
-                if let Some(f) = self.replies.borrow_mut().remove(&serial) {
-                    f(msg, self);
-                    continue;
-                }
-            }
-            let mut filters = self.filters.borrow_mut();
-            if let Some(k) = filters.iter_mut().find(|(_, v)| v.0.matches(&msg)).map(|(k, _)| *k) {
-                let mut v = filters.remove(&k).unwrap();
-                drop(filters);
-                if v.1(msg, &self) {
-                    let mut filters = self.filters.borrow_mut();
-                    filters.insert(k, v);
-                }
-                continue;
-            }
-            if let Some(reply) = crate::channel::default_reply(&msg) {
-                let _ = self.send(reply);
+    pub fn process_all(&self) { process_all_generic(self) }
+
+    /// Walks pending method call timeouts and fails the ones that have expired.
+    ///
+    /// This is called automatically by `process_all`, but is also exposed separately
+    /// so a reactor can run it between messages if it wants to fail timeouts promptly.
+    pub fn process_timeouts(&self) { process_timeouts_generic(self) }
+
+    /// Sets the default timeout used by `Proxy::method_call` (25 seconds unless changed).
+    pub fn set_default_timeout(&self, timeout: Duration) { Dispatch::set_default_timeout(self, timeout) }
+
+    /// The next point in time at which a pending method call will time out, if any.
+    ///
+    /// Useful for a reactor to compute how long it can safely sleep in `read_write`.
+    pub fn next_timeout(&self) -> Option<Instant> { Dispatch::next_timeout(self) }
+
+    /// Subscribes to messages matching `rule`, returning a [`Stream`] of them.
+    ///
+    /// The filter backing the stream is registered via `start_receive` and deregistered
+    /// again via `stop_receive` once the returned stream is dropped. Takes `self` by
+    /// `Rc` since the stream needs to keep the connection alive to deregister on drop;
+    /// call it as e.g. `conn.clone().add_match_stream(rule)` on an `Rc<Connection>`.
+    ///
+    /// Uses `DEFAULT_STREAM_CAPACITY` (64); once the stream's queue is full, further
+    /// matching messages are dropped. Use `add_match_stream_with_capacity` if that's not
+    /// enough headroom for a slow consumer.
+    pub fn add_match_stream(self: Rc<Self>, rule: MatchRule<'static>) -> MessageStream<Rc<Connection>> {
+        self.add_match_stream_with_capacity(rule, DEFAULT_STREAM_CAPACITY)
+    }
+
+    /// Like `add_match_stream`, but with an explicit bound on how many unconsumed
+    /// messages the stream will buffer before it starts dropping them.
+    pub fn add_match_stream_with_capacity(self: Rc<Self>, rule: MatchRule<'static>, capacity: usize) -> MessageStream<Rc<Connection>> {
+        let queue = Arc::new(StreamQueue::new(capacity));
+        let f = Connection::make_filter_cb(queue.clone());
+        let id = self.start_receive(rule, f);
+        MessageStream { queue, id, connection: self }
+    }
+
+    /// Opens a new connection to the session bus and performs the `Hello` handshake;
+    /// see `unique_name`.
+    pub async fn new_session() -> Result<Rc<Connection>, Error> {
+        Connection::hello(Channel::get_private(BusType::Session)?)
+    }
+
+    /// Opens a new connection to the system bus and performs the `Hello` handshake;
+    /// see `unique_name`.
+    pub async fn new_system() -> Result<Rc<Connection>, Error> {
+        Connection::hello(Channel::get_private(BusType::System)?)
+    }
+
+    /// Records the unique name the bus assigned during the `Hello` handshake.
+    ///
+    /// This can't go through `Proxy::method_call`: that returns a `MethodReply` which only
+    /// resolves once something calls `process_all` on *this* connection, but nothing can do
+    /// that until `hello` itself returns the `Connection` to the caller. `Channel::get_private`
+    /// already drives the `Hello` call synchronously inside libdbus before returning, so the
+    /// unique name is simply read back from the channel here.
+    fn hello(channel: Channel) -> Result<Rc<Connection>, Error> {
+        let unique_name = channel.unique_name();
+        let conn = Connection::from(channel);
+        *conn.unique_name.borrow_mut() = unique_name;
+        Ok(Rc::new(conn))
+    }
+
+    /// The unique name the message bus assigned to this connection during the `Hello`
+    /// handshake, if it was created via `new_session`/`new_system`.
+    pub fn unique_name(&self) -> Option<BusName<'static>> { self.unique_name.borrow().clone() }
+}
+
+impl MakeReplyCb for Connection {
+    type Cancel = dyn FnOnce();
+    fn make_reply_cb(mr: Arc<Mutex<MRInner>>) -> Self::F {
+        Box::new(move |msg: Result<Message, Error>, _: &Connection| {
+            let mut inner = mr.lock().unwrap();
+            let old = mem::replace(&mut *inner, MRInner::Ready(msg));
+            if let MRInner::Pending(waker) = old { waker.wake() }
+        })
+    }
+}
+
+impl<C> MakeCancel<C> for Connection where C: ops::Deref<Target = Connection> + Clone + 'static {
+    fn make_cancel(conn: C, serial: u32) -> Box<Self::Cancel> {
+        Box::new(move || { let _ = conn.cancel_reply(serial); })
+    }
+}
+
+impl MakeFilterCb for Connection {
+    fn make_filter_cb(queue: Arc<StreamQueue>) -> Self::F {
+        Box::new(move |msg: Message, _: &Connection| { queue.push(msg); FilterResult::Keep })
+    }
+}
+
+/// A `Send + Sync` version of [`Connection`].
+///
+/// Use this instead of [`Connection`] / [`LocalConnection`] when the connection needs to
+/// be shared between threads, e.g. wrapped in an `Arc` and handed to a multithreaded
+/// executor. It implements the same [`Sender`], [`NonblockReply`] and
+/// [`MatchingReceiver`] traits, but stores its replies and filters behind a `Mutex`
+/// instead of a `RefCell`, which means its callback boxes have to be `Send`.
+pub struct SyncConnection {
+    channel: Channel,
+    replies: Mutex<HashMap<u32, Box<dyn FnOnce(Result<Message, Error>, &SyncConnection) + Send>>>,
+    filters: Mutex<BTreeMap<u32, (MatchRule<'static>, Box<dyn FnMut(Message, &SyncConnection) -> FilterResult<SyncPendingFut> + Send>)>>,
+    filter_nextid: AtomicU32,
+    timeouts: Mutex<BTreeMap<Instant, Vec<u32>>>,
+    timeout_deadlines: Mutex<HashMap<u32, Instant>>,
+    default_timeout: Mutex<Duration>,
+    unique_name: Mutex<Option<BusName<'static>>>,
+    pending: Mutex<BTreeMap<PendingKey, (Message, SyncPendingFut)>>,
+}
+
+impl AsRef<Channel> for SyncConnection {
+    fn as_ref(&self) -> &Channel { &self.channel }
+}
+
+impl From<Channel> for SyncConnection {
+    fn from(x: Channel) -> Self {
+        SyncConnection {
+            channel: x,
+            replies: Default::default(),
+            filters: Default::default(),
+            filter_nextid: Default::default(),
+            timeouts: Default::default(),
+            timeout_deadlines: Default::default(),
+            default_timeout: Mutex::new(DEFAULT_TIMEOUT),
+            unique_name: Default::default(),
+            pending: Default::default(),
+        }
+    }
+}
+
+impl Sender for SyncConnection {
+    fn send(&self, msg: Message) -> Result<u32, ()> { self.channel.send(msg) }
+}
+
+impl NonblockReply for SyncConnection {
+    type F = Box<dyn FnOnce(Result<Message, Error>, &SyncConnection) + Send>;
+    fn send_with_reply(&self, msg: Message, f: Self::F) -> Result<u32, ()> {
+        self.channel.send(msg).map(|x| {
+            self.replies.lock().unwrap().insert(x, f);
+            x
+        })
+    }
+    fn cancel_reply(&self, id: u32) -> Option<Self::F> {
+        let f = self.replies.lock().unwrap().remove(&id);
+        if f.is_some() { self.remove_timeout(id); }
+        f
+    }
+}
+
+impl MatchingReceiver for SyncConnection {
+    type F = Box<dyn FnMut(Message, &SyncConnection) -> FilterResult<SyncPendingFut> + Send>;
+    fn start_receive(&self, m: MatchRule<'static>, f: Self::F) -> u32 {
+        let id = self.filter_nextid.fetch_add(1, Ordering::SeqCst);
+        self.filters.lock().unwrap().insert(id, (m, f));
+        id
+    }
+    fn stop_receive(&self, id: u32) -> Option<(MatchRule<'static>, Self::F)> {
+        self.filters.lock().unwrap().remove(&id)
+    }
+}
+
+impl Dispatch for SyncConnection {
+    type ReplyCb = Box<dyn FnOnce(Result<Message, Error>, &SyncConnection) + Send>;
+    type FilterCb = Box<dyn FnMut(Message, &SyncConnection) -> FilterResult<SyncPendingFut> + Send>;
+    type PendingFut = SyncPendingFut;
+
+    fn channel(&self) -> &Channel { &self.channel }
+    fn take_reply(&self, serial: u32) -> Option<Self::ReplyCb> { self.replies.lock().unwrap().remove(&serial) }
+    fn take_filter_for(&self, msg: &Message) -> Option<(u32, MatchRule<'static>, Self::FilterCb)> {
+        let mut filters = self.filters.lock().unwrap();
+        let k = filters.iter_mut().find(|(_, v)| v.0.matches(msg)).map(|(k, _)| *k)?;
+        let (m, f) = filters.remove(&k).unwrap();
+        Some((k, m, f))
+    }
+    fn reinsert_filter(&self, id: u32, m: MatchRule<'static>, f: Self::FilterCb) {
+        self.filters.lock().unwrap().insert(id, (m, f));
+    }
+    fn call_reply(f: Self::ReplyCb, msg: Result<Message, Error>, conn: &Self) { f(msg, conn) }
+    fn call_filter(f: &mut Self::FilterCb, msg: Message, conn: &Self) -> FilterResult<Self::PendingFut> { f(msg, conn) }
+
+    fn insert_pending(&self, orig: Message, fut: Self::PendingFut) {
+        self.pending.lock().unwrap().insert(pending_key(&orig), (orig, fut));
+    }
+    fn take_pending(&self) -> Vec<(Message, Self::PendingFut)> {
+        mem::replace(&mut *self.pending.lock().unwrap(), BTreeMap::new()).into_iter().map(|(_, v)| v).collect()
+    }
+    fn reinsert_pending(&self, orig: Message, fut: Self::PendingFut) {
+        self.pending.lock().unwrap().insert(pending_key(&orig), (orig, fut));
+    }
+
+    fn default_timeout(&self) -> Duration { *self.default_timeout.lock().unwrap() }
+    fn set_default_timeout(&self, timeout: Duration) { *self.default_timeout.lock().unwrap() = timeout }
+    fn insert_timeout(&self, serial: u32, deadline: Instant) {
+        self.timeouts.lock().unwrap().entry(deadline).or_insert_with(Vec::new).push(serial);
+        self.timeout_deadlines.lock().unwrap().insert(serial, deadline);
+    }
+    fn remove_timeout(&self, serial: u32) {
+        if let Some(deadline) = self.timeout_deadlines.lock().unwrap().remove(&serial) {
+            let mut timeouts = self.timeouts.lock().unwrap();
+            if let Some(serials) = timeouts.get_mut(&deadline) {
+                serials.retain(|&s| s != serial);
+                if serials.is_empty() { timeouts.remove(&deadline); }
             }
         }
     }
+    fn next_timeout(&self) -> Option<Instant> { self.timeouts.lock().unwrap().keys().next().copied() }
+    fn take_expired_timeouts(&self, now: Instant) -> Vec<u32> {
+        let mut timeouts = self.timeouts.lock().unwrap();
+        let expired: Vec<Instant> = timeouts.range(..=now).map(|(k, _)| *k).collect();
+        let mut serials = Vec::new();
+        for k in expired { serials.extend(timeouts.remove(&k).unwrap_or_default()); }
+        drop(timeouts);
+        let mut deadlines = self.timeout_deadlines.lock().unwrap();
+        for s in &serials { deadlines.remove(s); }
+        serials
+    }
 
+    fn send_with_reply_and_timeout(&self, msg: Message, f: Self::ReplyCb, deadline: Instant) -> Result<u32, ()> {
+        let serial = self.channel.send(msg)?;
+        // Hold `replies` locked across the timeout insert too: otherwise a reply for
+        // `serial` could be read and dispatched on another thread between the two
+        // inserts, leaving an orphaned timeout deadline with no matching reply entry.
+        let mut replies = self.replies.lock().unwrap();
+        replies.insert(serial, f);
+        self.insert_timeout(serial, deadline);
+        Ok(serial)
+    }
 }
 
+impl SyncConnection {
+    /// Reads/writes data to the connection, without blocking.
+    ///
+    /// This is usually called from the reactor when there is input on the file descriptor.
+    pub fn read_write(&self) -> Result<(), Error> { read_write_generic(self) }
+
+    /// Dispatches all pending messages, without blocking.
+    ///
+    /// This also processes expired method call timeouts; see `process_timeouts`.
+    /// This is usually called from the reactor only, after read_write.
+    pub fn process_all(&self) { process_all_generic(self) }
+
+    /// Walks pending method call timeouts and fails the ones that have expired.
+    ///
+    /// This is called automatically by `process_all`, but is also exposed separately
+    /// so a reactor can run it between messages if it wants to fail timeouts promptly.
+    pub fn process_timeouts(&self) { process_timeouts_generic(self) }
+
+    /// Sets the default timeout used by `Proxy::method_call` (25 seconds unless changed).
+    pub fn set_default_timeout(&self, timeout: Duration) { Dispatch::set_default_timeout(self, timeout) }
+
+    /// The next point in time at which a pending method call will time out, if any.
+    ///
+    /// Useful for a reactor to compute how long it can safely sleep in `read_write`.
+    pub fn next_timeout(&self) -> Option<Instant> { Dispatch::next_timeout(self) }
+
+    /// Subscribes to messages matching `rule`, returning a [`Stream`] of them.
+    ///
+    /// The filter backing the stream is registered via `start_receive` and deregistered
+    /// again via `stop_receive` once the returned stream is dropped. Takes `self` by
+    /// `Arc` since the stream needs to keep the connection alive to deregister on drop;
+    /// call it as e.g. `conn.clone().add_match_stream(rule)` on an `Arc<SyncConnection>`.
+    ///
+    /// Uses `DEFAULT_STREAM_CAPACITY` (64); once the stream's queue is full, further
+    /// matching messages are dropped. Use `add_match_stream_with_capacity` if that's not
+    /// enough headroom for a slow consumer.
+    pub fn add_match_stream(self: Arc<Self>, rule: MatchRule<'static>) -> MessageStream<Arc<SyncConnection>> {
+        self.add_match_stream_with_capacity(rule, DEFAULT_STREAM_CAPACITY)
+    }
+
+    /// Like `add_match_stream`, but with an explicit bound on how many unconsumed
+    /// messages the stream will buffer before it starts dropping them.
+    pub fn add_match_stream_with_capacity(self: Arc<Self>, rule: MatchRule<'static>, capacity: usize) -> MessageStream<Arc<SyncConnection>> {
+        let queue = Arc::new(StreamQueue::new(capacity));
+        let f = SyncConnection::make_filter_cb(queue.clone());
+        let id = self.start_receive(rule, f);
+        MessageStream { queue, id, connection: self }
+    }
+
+    /// Opens a new connection to the session bus and performs the `Hello` handshake;
+    /// see `unique_name`.
+    pub async fn new_session() -> Result<Arc<SyncConnection>, Error> {
+        SyncConnection::hello(Channel::get_private(BusType::Session)?)
+    }
 
+    /// Opens a new connection to the system bus and performs the `Hello` handshake;
+    /// see `unique_name`.
+    pub async fn new_system() -> Result<Arc<SyncConnection>, Error> {
+        SyncConnection::hello(Channel::get_private(BusType::System)?)
+    }
+
+    /// Records the unique name the bus assigned during the `Hello` handshake.
+    ///
+    /// See `Connection::hello` for why this reads the name back from the channel instead
+    /// of round-tripping a `Proxy::method_call` through a connection nothing is pumping yet.
+    fn hello(channel: Channel) -> Result<Arc<SyncConnection>, Error> {
+        let unique_name = channel.unique_name();
+        let conn = SyncConnection::from(channel);
+        *conn.unique_name.lock().unwrap() = unique_name;
+        Ok(Arc::new(conn))
+    }
+
+    /// The unique name the message bus assigned to this connection during the `Hello`
+    /// handshake, if it was created via `new_session`/`new_system`.
+    pub fn unique_name(&self) -> Option<BusName<'static>> { self.unique_name.lock().unwrap().clone() }
+}
+
+impl MakeReplyCb for SyncConnection {
+    type Cancel = dyn FnOnce() + Send + Sync;
+    fn make_reply_cb(mr: Arc<Mutex<MRInner>>) -> Self::F {
+        Box::new(move |msg: Result<Message, Error>, _: &SyncConnection| {
+            let mut inner = mr.lock().unwrap();
+            let old = mem::replace(&mut *inner, MRInner::Ready(msg));
+            if let MRInner::Pending(waker) = old { waker.wake() }
+        })
+    }
+}
+
+impl<C> MakeCancel<C> for SyncConnection
+where C: ops::Deref<Target = SyncConnection> + Clone + Send + Sync + 'static {
+    fn make_cancel(conn: C, serial: u32) -> Box<Self::Cancel> {
+        Box::new(move || { let _ = conn.cancel_reply(serial); })
+    }
+}
+
+impl MakeFilterCb for SyncConnection {
+    fn make_filter_cb(queue: Arc<StreamQueue>) -> Self::F {
+        Box::new(move |msg: Message, _: &SyncConnection| { queue.push(msg); FilterResult::Keep })
+    }
+}
 
 /// A struct that wraps a connection, destination and path.
 ///
-/// A D-Bus "Proxy" is a client-side object that corresponds to a remote object on the server side. 
+/// A D-Bus "Proxy" is a client-side object that corresponds to a remote object on the server side.
 /// Calling methods on the proxy object calls methods on the remote object.
 /// Read more in the [D-Bus tutorial](https://dbus.freedesktop.org/doc/dbus-tutorial.html#proxies)
 #[derive(Clone, Debug)]
@@ -132,42 +819,103 @@ pub struct Proxy<'a, C> {
 impl<'a, C> Proxy<'a, C> {
     /// Creates a new proxy struct.
     pub fn new<D: Into<BusName<'a>>, P: Into<Path<'a>>>(dest: D, path: P, connection: C) -> Self {
-        Proxy { destination: dest.into(), path: path.into(), connection } 
+        Proxy { destination: dest.into(), path: path.into(), connection }
     }
 }
 
-impl<'a, C: std::ops::Deref<Target=Connection>> Proxy<'a, C> {
-
+#[allow(private_bounds, private_interfaces)]
+impl<'a, T, C> Proxy<'a, C>
+where
+    T: NonblockReply + MakeReplyCb + MakeCancel<C> + Dispatch<ReplyCb = <T as NonblockReply>::F> + 'static,
+    C: ops::Deref<Target=T> + Clone + 'static,
+{
     /// Make a method call using typed input argument, returns a future that resolves to the typed output arguments.
+    ///
+    /// Uses the connection's default timeout; see `method_call_with_timeout` to override it.
+    /// Works for a `Proxy` wrapping any of this crate's connection types, e.g. `Rc<LocalConnection>`
+    /// or `Arc<SyncConnection>`.
     pub fn method_call<'i, 'm, R: ReadAll + 'static, A: AppendAll, I: Into<Interface<'i>>, M: Into<Member<'m>>>(&self, i: I, m: M, args: A)
-    -> MethodReply<R> {
+    -> MethodReply<R, T::Cancel> {
+        let timeout = Dispatch::default_timeout(&*self.connection);
+        self.method_call_with_timeout(i, m, args, timeout)
+    }
+
+    /// Make a method call using typed input argument, returns a future that resolves to the
+    /// typed output arguments, or an `org.freedesktop.DBus.Error.Timeout` error if the server
+    /// hasn't answered within `timeout`.
+    pub fn method_call_with_timeout<'i, 'm, R: ReadAll + 'static, A: AppendAll, I: Into<Interface<'i>>, M: Into<Member<'m>>>(&self, i: I, m: M, args: A, timeout: Duration)
+    -> MethodReply<R, T::Cancel> {
         let mut msg = Message::method_call(&self.destination, &self.path, &i.into(), &m.into());
         args.append(&mut IterAppend::new(&mut msg));
 
         let mr = Arc::new(Mutex::new(MRInner::Neither));
-        let mr2 = mr.clone();
-        let f = Box::new(move |msg: Message, _: &Connection| {
-            let mut inner = mr2.lock().unwrap();
-            let old = mem::replace(&mut *inner, MRInner::Ready(Ok(msg)));
-            if let MRInner::Pending(waker) = old { waker.wake() }
-        });
-        if let Err(_) = self.connection.send_with_reply(msg, f) {
-            *mr.lock().unwrap() = MRInner::Ready(Err(Error::new_failed("Failed to send message")));
-        }
-        MethodReply(mr, Some(Box::new(|msg: Message| { msg.read_all() })))
+        let f = T::make_reply_cb(mr.clone());
+        let conn = self.connection.clone();
+        let cancel: Option<Box<T::Cancel>> = match send_with_reply_and_timeout_generic(&*self.connection, msg, timeout, f) {
+            Ok(serial) => Some(T::make_cancel(conn, serial)),
+            Err(_) => {
+                *mr.lock().unwrap() = MRInner::Ready(Err(Error::new_failed("Failed to send message")));
+                None
+            }
+        };
+        MethodReply(mr, Some(Box::new(|msg: Message| { msg.read_all() })), cancel)
+    }
+}
+
+#[allow(private_bounds)]
+impl<'a, T, C> Proxy<'a, C>
+where
+    T: MatchingReceiver + MakeFilterCb + 'static,
+    C: ops::Deref<Target=T> + Clone + 'static,
+{
+    /// Subscribes to a specific signal (by interface and member) arriving at this
+    /// proxy's path, returning a [`Stream`] of the matching messages.
+    ///
+    /// This is a convenience wrapper around `add_match_stream` for the common case of
+    /// listening for one signal from one object path. Uses `DEFAULT_STREAM_CAPACITY`
+    /// (64); see `match_signal_stream_with_capacity` to pick a different bound on how
+    /// many unconsumed signals are buffered before they start being dropped.
+    pub fn match_signal_stream<'i, I: Into<Interface<'i>>, M: Into<Member<'i>>>(&self, interface: I, member: M) -> MessageStream<C> {
+        self.match_signal_stream_with_capacity(interface, member, DEFAULT_STREAM_CAPACITY)
+    }
+
+    /// Like `match_signal_stream`, but with an explicit bound on how many unconsumed
+    /// signals the stream will buffer before it starts dropping them.
+    pub fn match_signal_stream_with_capacity<'i, I: Into<Interface<'i>>, M: Into<Member<'i>>>(&self, interface: I, member: M, capacity: usize) -> MessageStream<C> {
+        let mut rule = MatchRule::new_signal(interface, member);
+        rule.path = Some(self.path.clone());
+        let rule = rule.static_clone();
+
+        let queue = Arc::new(StreamQueue::new(capacity));
+        let f = T::make_filter_cb(queue.clone());
+        let id = self.connection.start_receive(rule, f);
+        MessageStream { queue, id, connection: self.connection.clone() }
     }
 }
 
-enum MRInner {
+pub(crate) enum MRInner {
     Ready(Result<Message, Error>),
     Pending(task::Waker),
     Neither,
 }
 
 /// Future method reply, used while waiting for a method call reply from the server.
-pub struct MethodReply<T>(Arc<Mutex<MRInner>>, Option<Box<FnOnce(Message) -> Result<T, Error> + Send + Sync + 'static>>); 
+///
+/// If dropped before it resolves, the pending reply callback is evicted from the
+/// connection via the stored cancellation token, so the connection doesn't keep a
+/// (potentially never-called) callback around forever.
+///
+/// `Z` is the cancellation token's trait object type - `dyn FnOnce()` for a `Proxy` over
+/// `Rc<Connection>`, or `dyn FnOnce() + Send + Sync` for one over `Arc<SyncConnection>`
+/// (see [`MakeReplyCb::Cancel`]) - so a `MethodReply` backed by a `Send` connection is
+/// itself `Send`, instead of being unconditionally `!Send`.
+pub struct MethodReply<T, Z: FnOnce() + ?Sized + 'static = dyn FnOnce()>(
+    Arc<Mutex<MRInner>>,
+    Option<Box<FnOnce(Message) -> Result<T, Error> + Send + Sync + 'static>>,
+    Option<Box<Z>>,
+);
 
-impl<T> future::Future for MethodReply<T> {
+impl<T, Z: FnOnce() + ?Sized + 'static> future::Future for MethodReply<T, Z> {
     type Output = Result<T, Error>;
     fn poll(mut self: pin::Pin<&mut Self>, ctx: &mut task::Context) -> task::Poll<Result<T, Error>> {
         let r = {
@@ -179,19 +927,24 @@ impl<T> future::Future for MethodReply<T> {
                 return task::Poll::Pending
             }
         };
+        self.2.take();
         let readfn = self.1.take().expect("Polled MethodReply after Ready");
         task::Poll::Ready(r.and_then(readfn))
     }
 }
 
-impl<T: 'static> MethodReply<T> {
-    /// Convenience combinator in case you want to post-process the result after reading it
-    pub fn and_then<T2>(self, f: impl FnOnce(T) -> Result<T2, Error> + Send + Sync + 'static) -> MethodReply<T2> {
-        let MethodReply(inner, first) = self;
-        MethodReply(inner, Some({
-            let first = first.unwrap();
-            Box::new(|r| first(r).and_then(f))
-        }))
+impl<T, Z: FnOnce() + ?Sized + 'static> Drop for MethodReply<T, Z> {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.2.take() { cancel() }
     }
 }
 
+impl<T: 'static, Z: FnOnce() + ?Sized + 'static> MethodReply<T, Z> {
+    /// Convenience combinator in case you want to post-process the result after reading it
+    pub fn and_then<T2>(mut self, f: impl FnOnce(T) -> Result<T2, Error> + Send + Sync + 'static) -> MethodReply<T2, Z> {
+        let inner = self.0.clone();
+        let first = self.1.take().expect("and_then called after Ready");
+        let cancel = self.2.take();
+        MethodReply(inner, Some(Box::new(|r| first(r).and_then(f))), cancel)
+    }
+}